@@ -0,0 +1,924 @@
+//! バーコード領域検出のコアパイプライン。
+//!
+//! 画像をセクションに分割し、Sobel勾配でテクスチャを足切りしたうえで2次元FFTのスコアを求め、
+//! 連結成分ラベリングでバーコードらしき矩形領域 (`Rect`) を返す。ファイルの読み書きは
+//! `BarcodeDetector::save_sections` / `BarcodeDetector::plot` のオプトインヘルパーに切り出されており、
+//! `BarcodeDetector::detect` 自体は副作用を持たないため、合成したテスト画像にも適用できる。
+
+use image::{GenericImageView, GrayImage};
+use rustfft::num_complex::Complex;
+use rustfft::FftPlanner;
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+/// 検出パイプラインの挙動を調整するパラメータ一式。
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// 横方向のセクション数
+    pub num_sections_width: u32,
+    /// 縦方向のセクション高さ（ピクセル）
+    pub section_height: u32,
+    /// FFTスコアの振幅しきい値
+    pub threshold: f32,
+    /// バーコード領域とみなす最小のブロブ面積（セクション単位のセル数）
+    pub min_blob_area: usize,
+    /// Sobel 二値化のしきい値（セクション内最大勾配に対する割合）
+    pub sobel_edge_threshold_fraction: f32,
+    /// 水平方向勾配エネルギーが垂直方向勾配エネルギーの何倍以上あればバーコード候補とみなすか
+    pub anisotropy_ratio_cutoff: f32,
+    /// 候補とみなすために必要なエッジ画素数の下限
+    pub min_edge_pixels: u32,
+    /// DC近傍とみなして積分対象から除く列方向のガードバンド幅
+    pub freq_band_col_guard: usize,
+    /// DC行からどれだけ離れた行まで積分対象に含めるか
+    pub freq_band_row_margin: usize,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            num_sections_width: 60,
+            section_height: 100,
+            threshold: 50.0,
+            min_blob_area: 5,
+            sobel_edge_threshold_fraction: 0.3,
+            anisotropy_ratio_cutoff: 1.5,
+            min_edge_pixels: 20,
+            freq_band_col_guard: 2,
+            freq_band_row_margin: 2,
+        }
+    }
+}
+
+/// チャートの出力先。SSH 越しや CI のログではビットマップが見られないため、
+/// `--text` 指定時は ASCII のブロック文字で同じ情報を stdout に描く。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenderBackend {
+    Bitmap,
+    Text,
+}
+
+impl RenderBackend {
+    /// コマンドライン引数 (`--text` / `--png`) または環境変数 `BARCODE_RENDER=text` から描画モードを決める
+    pub fn from_env() -> Self {
+        if std::env::args().any(|arg| arg == "--text") {
+            return RenderBackend::Text;
+        }
+        if std::env::args().any(|arg| arg == "--png") {
+            return RenderBackend::Bitmap;
+        }
+        match std::env::var("BARCODE_RENDER").as_deref() {
+            Ok("text") => RenderBackend::Text,
+            _ => RenderBackend::Bitmap,
+        }
+    }
+}
+
+/// ピクセル空間の矩形領域。バーコード検出・合成・描画で共通して使う座標表現。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rect {
+    pub min: (u32, u32),
+    pub max: (u32, u32),
+}
+
+impl Rect {
+    pub fn new(min: (u32, u32), max: (u32, u32)) -> Self {
+        Rect { min, max }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.max.0 - self.min.0
+    }
+
+    pub fn height(&self) -> u32 {
+        self.max.1 - self.min.1
+    }
+
+    pub fn area(&self) -> u32 {
+        self.width() * self.height()
+    }
+
+    pub fn contains(&self, x: u32, y: u32) -> bool {
+        x >= self.min.0 && x < self.max.0 && y >= self.min.1 && y < self.max.1
+    }
+
+    /// 2つの矩形が重なる部分。重ならない場合は `None`。
+    pub fn intersection(&self, other: &Rect) -> Option<Rect> {
+        let min = (self.min.0.max(other.min.0), self.min.1.max(other.min.1));
+        let max = (self.max.0.min(other.max.0), self.max.1.min(other.max.1));
+        if min.0 < max.0 && min.1 < max.1 {
+            Some(Rect::new(min, max))
+        } else {
+            None
+        }
+    }
+
+    /// 2つの矩形を両方とも内包する最小の矩形。
+    pub fn union(&self, other: &Rect) -> Rect {
+        Rect::new(
+            (self.min.0.min(other.min.0), self.min.1.min(other.min.1)),
+            (self.max.0.max(other.max.0), self.max.1.max(other.max.1)),
+        )
+    }
+}
+
+/// 画像からバーコード候補領域を検出するパイプライン本体。
+pub struct BarcodeDetector {
+    config: Config,
+}
+
+impl BarcodeDetector {
+    pub fn new(config: Config) -> Self {
+        BarcodeDetector { config }
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    fn section_dims(&self, img: &GrayImage) -> (u32, u32, usize) {
+        let section_width = img.width() / self.config.num_sections_width;
+        let section_height = self.config.section_height;
+        let num_sections_height = (img.height() / section_height) as usize;
+        (section_width, section_height, num_sections_height)
+    }
+
+    /// 全セクションの振幅グリッドを計算する。ファイルの読み書きは行わない。
+    pub fn magnitude_grid(&self, img: &GrayImage) -> Vec<Vec<f32>> {
+        let (section_width, section_height, num_sections_height) = self.section_dims(img);
+        let mut magnitude_grid = Vec::with_capacity(num_sections_height);
+
+        for j in 0..num_sections_height {
+            let section_y_start = j as u32 * section_height;
+            let mut section_magnitudes =
+                Vec::with_capacity(self.config.num_sections_width as usize);
+
+            for i in 0..self.config.num_sections_width {
+                let section_x_start = i * section_width;
+                let section_view = img.view(
+                    section_x_start,
+                    section_y_start,
+                    section_width,
+                    section_height,
+                );
+                let section_image = section_view.to_image();
+
+                // FFT にかける前に Sobel 勾配でテクスチャを足切りする（文字や植生などの高周波ノイズ除去）
+                if !self.is_barcode_candidate(&section_image) {
+                    section_magnitudes.push(0.0);
+                    continue;
+                }
+
+                // セクション全体に対して真の2次元FFTをかけ、中心線1本より頑健なスコアを得る
+                let section_magnitude = self.section_2d_fft_magnitude(&section_image);
+
+                // 振幅の合計値がしきい値を超える場合のみ記録
+                if section_magnitude > self.config.threshold {
+                    section_magnitudes.push(section_magnitude);
+                } else {
+                    section_magnitudes.push(0.0);
+                }
+            }
+
+            magnitude_grid.push(section_magnitudes);
+        }
+
+        magnitude_grid
+    }
+
+    /// 振幅グリッドに連結成分ラベリングをかけ、バーコード領域のバウンディングボックスを求める。
+    /// セクション単位のラベリングでは隣接セクションをまたいで重なる矩形が残ることがあるため、
+    /// 仕上げに `merge_overlapping_regions` でピクセル空間での重なりを解消する。
+    pub fn regions_from_grid(&self, grid: &[Vec<f32>], img: &GrayImage) -> Vec<Rect> {
+        let (section_width, section_height, _) = self.section_dims(img);
+        let regions = label_regions(
+            grid,
+            section_width,
+            section_height,
+            self.config.min_blob_area,
+        );
+        merge_overlapping_regions(regions)
+    }
+
+    /// 画像からバーコード領域を検出する。ファイルの読み書きは一切行わない。
+    pub fn detect(&self, img: &GrayImage) -> Vec<Rect> {
+        let grid = self.magnitude_grid(img);
+        self.regions_from_grid(&grid, img)
+    }
+
+    /// 各縦セクションをPNGとして書き出す（オプトイン）
+    pub fn save_sections(&self, img: &GrayImage, output_dir: &Path) -> Result<(), Box<dyn Error>> {
+        if !output_dir.exists() {
+            fs::create_dir_all(output_dir)?;
+        }
+
+        let (_, section_height, num_sections_height) = self.section_dims(img);
+        for j in 0..num_sections_height {
+            let section_y_start = j as u32 * section_height;
+            let section_image = img.view(0, section_y_start, img.width(), section_height);
+            let section_image = GrayImage::from(section_image.to_image());
+            let output_path = output_dir.join(format!("section_{}.png", j));
+            section_image.save(&output_path)?;
+            println!("Saved section image: {}", output_path.display());
+        }
+
+        Ok(())
+    }
+
+    /// 検出結果のグラフ／ヒートマップを描画する（オプトイン）
+    pub fn plot(
+        &self,
+        img: &GrayImage,
+        grid: &[Vec<f32>],
+        regions: &[Rect],
+        backend: RenderBackend,
+    ) {
+        let (section_width, section_height, _) = self.section_dims(img);
+
+        for (j, section_magnitudes) in grid.iter().enumerate() {
+            plot_section_magnitudes(
+                section_magnitudes,
+                regions,
+                j,
+                section_width,
+                section_height,
+                backend,
+            );
+        }
+
+        plot_magnitude_heatmap(grid, regions, section_width, section_height, backend);
+    }
+
+    // Sobel 勾配を計算し、水平方向の勾配エネルギーが卓越している（＝垂直なバーが並ぶバーコード特有の特徴を持つ）
+    // セクションだけを候補として残す
+    fn is_barcode_candidate(&self, section: &GrayImage) -> bool {
+        const SOBEL_X: [[i32; 3]; 3] = [[-1, 0, 1], [-2, 0, 2], [-1, 0, 1]];
+        const SOBEL_Y: [[i32; 3]; 3] = [[-1, -2, -1], [0, 0, 0], [1, 2, 1]];
+
+        let (width, height) = section.dimensions();
+        if width < 3 || height < 3 {
+            return false;
+        }
+
+        let mut gradients = Vec::with_capacity(((width - 2) * (height - 2)) as usize);
+        let mut max_gradient = 0.0f32;
+
+        for y in 1..height - 1 {
+            for x in 1..width - 1 {
+                let mut gx = 0i32;
+                let mut gy = 0i32;
+                for ky in 0..3 {
+                    for kx in 0..3 {
+                        let pixel = section.get_pixel(x + kx - 1, y + ky - 1)[0] as i32;
+                        gx += SOBEL_X[ky as usize][kx as usize] * pixel;
+                        gy += SOBEL_Y[ky as usize][kx as usize] * pixel;
+                    }
+                }
+                let magnitude = ((gx * gx + gy * gy) as f32).sqrt();
+                max_gradient = max_gradient.max(magnitude);
+                gradients.push((gx as f32, gy as f32, magnitude));
+            }
+        }
+
+        if max_gradient == 0.0 {
+            return false;
+        }
+
+        let edge_threshold = max_gradient * self.config.sobel_edge_threshold_fraction;
+        let mut horizontal_energy = 0.0f32;
+        let mut vertical_energy = 0.0f32;
+        let mut edge_pixels = 0u32;
+
+        for &(gx, gy, magnitude) in &gradients {
+            if magnitude >= edge_threshold {
+                horizontal_energy += gx * gx;
+                vertical_energy += gy * gy;
+                edge_pixels += 1;
+            }
+        }
+
+        if edge_pixels < self.config.min_edge_pixels {
+            return false;
+        }
+
+        // vertical_energy == 0.0 は縦バーのみで縦方向の勾配が皆無の理想的なバーコード断面であり、
+        // 比率は +inf になって cutoff 判定を自然に満たす（edge_pixels > 0 かつ max_gradient > 0 が
+        // 保証された後なので 0.0 / 0.0 の NaN にはならない）
+        let anisotropy_ratio = horizontal_energy / vertical_energy;
+        anisotropy_ratio >= self.config.anisotropy_ratio_cutoff
+    }
+
+    // セクション全体に2次元FFTをかけ、水平空間周波数帯のエネルギーを積分してスコア化する。
+    // 中心1ラインのサンプリングと違い、ノイズ・傾き・部分的な遮蔽に対して頑健。
+    fn section_2d_fft_magnitude(&self, section: &GrayImage) -> f32 {
+        let (width, height) = section.dimensions();
+        let (width, height) = (width as usize, height as usize);
+
+        let mut rows: Vec<Vec<Complex<f32>>> = (0..height)
+            .map(|y| {
+                (0..width)
+                    .map(|x| {
+                        let pixel = section.get_pixel(x as u32, y as u32)[0];
+                        let value = if pixel > 128 { 1.0 } else { 0.0 };
+                        Complex::new(value, 0.0)
+                    })
+                    .collect()
+            })
+            .collect();
+
+        // 行方向（横）のFFT
+        let mut planner = FftPlanner::<f32>::new();
+        if width > 0 {
+            let row_fft = planner.plan_fft_forward(width);
+            for row in rows.iter_mut() {
+                row_fft.process(row);
+            }
+        }
+
+        // 列方向（縦）のFFT
+        if height > 0 {
+            let col_fft = planner.plan_fft_forward(height);
+            let mut columns = transpose(&rows);
+            for column in columns.iter_mut() {
+                col_fft.process(column);
+            }
+            rows = transpose(&columns);
+        }
+
+        fft_shift(&mut rows);
+
+        let center_row = height / 2;
+        let center_col = width / 2;
+        let row_range = center_row.saturating_sub(self.config.freq_band_row_margin)
+            ..=(center_row + self.config.freq_band_row_margin).min(height.saturating_sub(1));
+
+        let mut magnitude_sum = 0.0f32;
+        for y in row_range {
+            for (x, c) in rows[y].iter().enumerate() {
+                let col_distance = (x as isize - center_col as isize).unsigned_abs();
+                if col_distance > self.config.freq_band_col_guard {
+                    magnitude_sum += (c.re * c.re + c.im * c.im).sqrt();
+                }
+            }
+        }
+
+        magnitude_sum
+    }
+}
+
+// 行と列を入れ替える（縦横FFTの中間転置、および fftshift の列方向シフトに使う）
+fn transpose(matrix: &[Vec<Complex<f32>>]) -> Vec<Vec<Complex<f32>>> {
+    let height = matrix.len();
+    let width = matrix.first().map_or(0, |row| row.len());
+    let mut result = vec![Vec::with_capacity(height); width];
+
+    for row in matrix {
+        for (x, &value) in row.iter().enumerate() {
+            result[x].push(value);
+        }
+    }
+
+    result
+}
+
+// DCがスペクトル中心に来るよう象限を入れ替える（fftshift）。奇数次元は標準的な floor 分割で扱う
+fn fft_shift(matrix: &mut [Vec<Complex<f32>>]) {
+    let height = matrix.len();
+    if height == 0 {
+        return;
+    }
+    let width = matrix[0].len();
+    if width == 0 {
+        return;
+    }
+
+    let col_shift = width.div_ceil(2);
+    for row in matrix.iter_mut() {
+        row.rotate_left(col_shift);
+    }
+
+    let row_shift = height.div_ceil(2);
+    let mut columns = transpose(matrix);
+    for column in columns.iter_mut() {
+        column.rotate_left(row_shift);
+    }
+    for (row, shifted_row) in matrix.iter_mut().zip(transpose(&columns)) {
+        *row = shifted_row;
+    }
+}
+
+// 行内の連続する前景（非ゼロ）セクションの開始・終了列を求める
+fn row_runs(row: &[f32]) -> Vec<(usize, usize)> {
+    let mut runs = Vec::new();
+    let mut start: Option<usize> = None;
+
+    for (col, &magnitude) in row.iter().enumerate() {
+        if magnitude > 0.0 {
+            if start.is_none() {
+                start = Some(col);
+            }
+        } else if let Some(s) = start.take() {
+            runs.push((s, col - 1));
+        }
+    }
+    if let Some(s) = start {
+        runs.push((s, row.len() - 1));
+    }
+
+    runs
+}
+
+// 2つの列区間が重なっているか
+fn ranges_overlap(a: (usize, usize), b: (usize, usize)) -> bool {
+    a.0 <= b.1 && b.0 <= a.1
+}
+
+// シンプルな Union-Find（経路圧縮付き）
+struct UnionFind {
+    parent: Vec<usize>,
+}
+
+impl UnionFind {
+    fn new(n: usize) -> Self {
+        UnionFind {
+            parent: (0..n).collect(),
+        }
+    }
+
+    fn find(&mut self, x: usize) -> usize {
+        if self.parent[x] != x {
+            self.parent[x] = self.find(self.parent[x]);
+        }
+        self.parent[x]
+    }
+
+    fn union(&mut self, a: usize, b: usize) {
+        let (ra, rb) = (self.find(a), self.find(b));
+        if ra != rb {
+            self.parent[ra.max(rb)] = ra.min(rb);
+        }
+    }
+}
+
+// グリッド（行 = 縦方向セクション、列 = 横方向セクション）を二値画像とみなし、
+// 行ごとのランレングスで連結成分ラベリングを行い、ブロブごとのピクセル座標バウンディングボックスを返す
+fn label_regions(
+    grid: &[Vec<f32>],
+    section_width: u32,
+    section_height: u32,
+    min_blob_area: usize,
+) -> Vec<Rect> {
+    // 各行のラン一覧と、そのランに割り当てられたラベル
+    let mut row_labels: Vec<Vec<usize>> = Vec::with_capacity(grid.len());
+    let mut all_runs: Vec<Vec<(usize, usize)>> = Vec::with_capacity(grid.len());
+    let mut uf = UnionFind::new(0);
+    let mut next_label = 0usize;
+
+    for (row_idx, row) in grid.iter().enumerate() {
+        let runs = row_runs(row);
+        let mut labels_for_row = Vec::with_capacity(runs.len());
+
+        for &run in &runs {
+            let mut matched_label: Option<usize> = None;
+
+            if row_idx > 0 {
+                for (prev_run, &prev_label) in all_runs[row_idx - 1]
+                    .iter()
+                    .zip(row_labels[row_idx - 1].iter())
+                {
+                    if ranges_overlap(run, *prev_run) {
+                        match matched_label {
+                            None => matched_label = Some(prev_label),
+                            Some(existing) if existing != prev_label => {
+                                uf.union(existing, prev_label);
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+            }
+
+            let label = matched_label.unwrap_or_else(|| {
+                let label = next_label;
+                next_label += 1;
+                uf.parent.push(label);
+                label
+            });
+            labels_for_row.push(label);
+        }
+
+        all_runs.push(runs);
+        row_labels.push(labels_for_row);
+    }
+
+    // 等価ラベルを解決しつつ、ラベルごとのセクション空間バウンディングボックス（Rect::union で拡張）とセル数を集計
+    use std::collections::HashMap;
+    struct Bounds {
+        rect: Rect,
+        area: usize,
+    }
+    let mut bounds: HashMap<usize, Bounds> = HashMap::new();
+
+    for (row_idx, (runs, labels)) in all_runs.iter().zip(row_labels.iter()).enumerate() {
+        for (&(col_start, col_end), &label) in runs.iter().zip(labels.iter()) {
+            let root = uf.find(label);
+            let run_rect = Rect::new(
+                (col_start as u32, row_idx as u32),
+                (col_end as u32 + 1, row_idx as u32 + 1),
+            );
+            let entry = bounds.entry(root).or_insert(Bounds {
+                rect: run_rect,
+                area: 0,
+            });
+            entry.rect = entry.rect.union(&run_rect);
+            entry.area += col_end - col_start + 1;
+        }
+    }
+
+    bounds
+        .values()
+        .filter(|b| b.area >= min_blob_area)
+        .map(|b| {
+            Rect::new(
+                (b.rect.min.0 * section_width, b.rect.min.1 * section_height),
+                (b.rect.max.0 * section_width, b.rect.max.1 * section_height),
+            )
+        })
+        .collect()
+}
+
+// 重なり合う矩形を `Rect::intersection`/`Rect::union` で1つにまとめる。どの対も重ならなくなるまで繰り返す
+fn merge_overlapping_regions(regions: Vec<Rect>) -> Vec<Rect> {
+    let mut merged = regions;
+
+    loop {
+        let mut did_merge = false;
+
+        'outer: for i in 0..merged.len() {
+            for j in (i + 1)..merged.len() {
+                if merged[i].intersection(&merged[j]).is_some() {
+                    merged[i] = merged[i].union(&merged[j]);
+                    merged.remove(j);
+                    did_merge = true;
+                    break 'outer;
+                }
+            }
+        }
+
+        if !did_merge {
+            break;
+        }
+    }
+
+    merged
+}
+
+// グラフプロット関数（ビットマップ／テキストの描画先を振り分ける）
+fn plot_section_magnitudes(
+    magnitudes: &[f32],
+    barcode_regions: &[Rect],
+    section_num: usize,
+    section_width: u32,
+    section_height: u32,
+    backend: RenderBackend,
+) {
+    match backend {
+        RenderBackend::Bitmap => plot_section_magnitudes_bitmap(
+            magnitudes,
+            barcode_regions,
+            section_num,
+            section_width,
+            section_height,
+        ),
+        RenderBackend::Text => plot_section_magnitudes_text(
+            magnitudes,
+            barcode_regions,
+            section_num,
+            section_width,
+            section_height,
+        ),
+    }
+}
+
+fn plot_section_magnitudes_bitmap(
+    magnitudes: &[f32],
+    barcode_regions: &[Rect],
+    section_num: usize,
+    section_width: u32,
+    section_height: u32,
+) {
+    use plotters::prelude::*;
+
+    let filename = format!("assets/section_magnitudes_{}_height.png", section_num);
+    let root = BitMapBackend::new(&filename, (800, 600)).into_drawing_area();
+    root.fill(&WHITE).unwrap();
+
+    let max_magnitude = magnitudes.iter().cloned().fold(f32::NAN, f32::max);
+
+    let mut chart = ChartBuilder::on(&root)
+        .caption(
+            format!("Height Section {} - 周波数成分の強度", section_num),
+            ("sans-serif", 20),
+        )
+        .margin(10)
+        .x_label_area_size(30)
+        .y_label_area_size(30)
+        .build_cartesian_2d(0..magnitudes.len(), 0f32..max_magnitude)
+        .unwrap();
+
+    chart.configure_mesh().draw().unwrap();
+
+    // 棒グラフとして表示
+    chart
+        .draw_series(magnitudes.iter().enumerate().map(|(i, &mag)| {
+            let x_pos = i as u32 * section_width;
+            let y_pos = section_num as u32 * section_height;
+            let color = if barcode_regions
+                .iter()
+                .any(|rect| rect.contains(x_pos, y_pos))
+            {
+                &RED
+            } else {
+                &BLUE
+            };
+            Rectangle::new([(i, 0.0), (i + 1, mag)], color.filled())
+        }))
+        .unwrap();
+}
+
+// 棒グラフの高さをブロック文字の行数に変換し、ANSIカラーでビットマップ版の赤/青の区別を再現して stdout に描く
+fn plot_section_magnitudes_text(
+    magnitudes: &[f32],
+    barcode_regions: &[Rect],
+    section_num: usize,
+    section_width: u32,
+    section_height: u32,
+) {
+    const CHART_ROWS: usize = 15;
+    const RED: &str = "\x1b[31m";
+    const BLUE: &str = "\x1b[34m";
+    const RESET: &str = "\x1b[0m";
+
+    let max_magnitude = magnitudes.iter().cloned().fold(0.0f32, f32::max);
+    println!(
+        "Height Section {} - 周波数成分の強度 (text mode)",
+        section_num
+    );
+    if max_magnitude == 0.0 {
+        println!("(no signal)");
+        return;
+    }
+
+    for row in (0..CHART_ROWS).rev() {
+        let row_threshold = (row as f32 + 1.0) / CHART_ROWS as f32 * max_magnitude;
+        let mut line = String::new();
+        for (i, &mag) in magnitudes.iter().enumerate() {
+            let x_pos = i as u32 * section_width;
+            let y_pos = section_num as u32 * section_height;
+            let in_region = barcode_regions
+                .iter()
+                .any(|rect| rect.contains(x_pos, y_pos));
+            let color = if in_region { RED } else { BLUE };
+            if mag >= row_threshold {
+                line.push_str(color);
+                line.push('█');
+                line.push_str(RESET);
+            } else {
+                line.push(' ');
+            }
+        }
+        println!("{}", line);
+    }
+    println!("{}", "-".repeat(magnitudes.len()));
+}
+
+// 正規化した振幅（0.0〜1.0）を青→赤のカラーランプにマッピングする
+fn magnitude_to_color(normalized: f32) -> plotters::style::RGBColor {
+    use plotters::style::RGBColor;
+
+    let t = normalized.clamp(0.0, 1.0);
+    RGBColor((t * 255.0) as u8, 0, ((1.0 - t) * 255.0) as u8)
+}
+
+// 画像全体のセクション振幅グリッドを1枚のヒートマップ（matshow相当）として描画し、
+// 検出されたバーコード領域を枠線で重ね描きする（ビットマップ／テキストの描画先を振り分ける）
+fn plot_magnitude_heatmap(
+    grid: &[Vec<f32>],
+    barcode_regions: &[Rect],
+    section_width: u32,
+    section_height: u32,
+    backend: RenderBackend,
+) {
+    match backend {
+        RenderBackend::Bitmap => {
+            plot_magnitude_heatmap_bitmap(grid, barcode_regions, section_width, section_height)
+        }
+        RenderBackend::Text => {
+            plot_magnitude_heatmap_text(grid, barcode_regions, section_width, section_height)
+        }
+    }
+}
+
+fn plot_magnitude_heatmap_bitmap(
+    grid: &[Vec<f32>],
+    barcode_regions: &[Rect],
+    section_width: u32,
+    section_height: u32,
+) {
+    use plotters::prelude::*;
+
+    let num_sections_height = grid.len();
+    let num_sections_width = grid.first().map_or(0, |row| row.len());
+    if num_sections_height == 0 || num_sections_width == 0 {
+        return;
+    }
+
+    let max_magnitude = grid
+        .iter()
+        .flat_map(|row| row.iter().cloned())
+        .fold(0.0f32, f32::max);
+
+    let filename = "assets/magnitude_heatmap.png";
+    let root = BitMapBackend::new(filename, (800, 600)).into_drawing_area();
+    root.fill(&WHITE).unwrap();
+
+    let mut chart = ChartBuilder::on(&root)
+        .caption("周波数エネルギー分布ヒートマップ", ("sans-serif", 20))
+        .margin(10)
+        .x_label_area_size(30)
+        .y_label_area_size(30)
+        .build_cartesian_2d(0..num_sections_width, 0..num_sections_height)
+        .unwrap();
+
+    chart.configure_mesh().draw().unwrap();
+
+    // plotters のy軸は上に向かって増加するため、画像上端が行0となるグリッド行をそのまま描くと
+    // 上下が反転してしまう。行をチャート座標に変換する際に上下を入れ替えて画像と向きを揃える
+    let flip_row = move |row: usize| num_sections_height - row;
+
+    // セクションごとのセルを1枚の塗りつぶし矩形として描画
+    chart
+        .draw_series(grid.iter().enumerate().flat_map(|(row, magnitudes)| {
+            magnitudes.iter().enumerate().map(move |(col, &mag)| {
+                let normalized = if max_magnitude > 0.0 {
+                    mag / max_magnitude
+                } else {
+                    0.0
+                };
+                Rectangle::new(
+                    [(col, flip_row(row + 1)), (col + 1, flip_row(row))],
+                    magnitude_to_color(normalized).filled(),
+                )
+            })
+        }))
+        .unwrap();
+
+    // 検出済みのバーコード領域をセクション単位に変換し、枠線だけの矩形として重ね描き
+    chart
+        .draw_series(barcode_regions.iter().map(|rect| {
+            let col_start = (rect.min.0 / section_width) as usize;
+            let col_end = (rect.max.0 / section_width) as usize;
+            let row_start = (rect.min.1 / section_height) as usize;
+            let row_end = (rect.max.1 / section_height) as usize;
+            Rectangle::new(
+                [
+                    (col_start, flip_row(row_end)),
+                    (col_end, flip_row(row_start)),
+                ],
+                BLACK.stroke_width(2),
+            )
+        }))
+        .unwrap();
+}
+
+// ヒートマップを濃淡を表すASCII文字の行列として stdout に描く。検出領域内のセルは赤で強調する
+fn plot_magnitude_heatmap_text(
+    grid: &[Vec<f32>],
+    barcode_regions: &[Rect],
+    section_width: u32,
+    section_height: u32,
+) {
+    const RAMP: &[char] = &[' ', '.', ':', '-', '=', '+', '*', '#', '%', '@'];
+    const RED: &str = "\x1b[31m";
+    const RESET: &str = "\x1b[0m";
+
+    let max_magnitude = grid
+        .iter()
+        .flat_map(|row| row.iter().cloned())
+        .fold(0.0f32, f32::max);
+
+    println!("周波数エネルギー分布ヒートマップ (text mode)");
+
+    for (row, magnitudes) in grid.iter().enumerate() {
+        let mut line = String::new();
+        for (col, &mag) in magnitudes.iter().enumerate() {
+            let x_pos = col as u32 * section_width;
+            let y_pos = row as u32 * section_height;
+            let in_region = barcode_regions
+                .iter()
+                .any(|rect| rect.contains(x_pos, y_pos));
+
+            let normalized = if max_magnitude > 0.0 {
+                mag / max_magnitude
+            } else {
+                0.0
+            };
+            let ramp_index = ((normalized.clamp(0.0, 1.0)) * (RAMP.len() - 1) as f32) as usize;
+            let glyph = RAMP[ramp_index];
+
+            if in_region {
+                line.push_str(RED);
+                line.push(glyph);
+                line.push_str(RESET);
+            } else {
+                line.push(glyph);
+            }
+        }
+        println!("{}", line);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rect_union_covers_both_rects() {
+        let a = Rect::new((0, 0), (4, 4));
+        let b = Rect::new((2, 2), (8, 6));
+        assert_eq!(a.union(&b), Rect::new((0, 0), (8, 6)));
+    }
+
+    #[test]
+    fn rect_intersection_returns_overlap() {
+        let a = Rect::new((0, 0), (4, 4));
+        let b = Rect::new((2, 2), (8, 6));
+        assert_eq!(a.intersection(&b), Some(Rect::new((2, 2), (4, 4))));
+    }
+
+    #[test]
+    fn rect_intersection_is_none_when_disjoint() {
+        let a = Rect::new((0, 0), (2, 2));
+        let b = Rect::new((3, 3), (5, 5));
+        assert_eq!(a.intersection(&b), None);
+    }
+
+    // 縦に2分割されたバンド（行）にまたがるブロブが、連結成分ラベリングで1つの矩形に統合されることを検証する
+    #[test]
+    fn label_regions_merges_blob_spanning_two_row_bands() {
+        let grid = vec![
+            vec![0.0, 10.0, 10.0, 10.0, 0.0],
+            vec![0.0, 0.0, 10.0, 10.0, 10.0],
+        ];
+        let regions = label_regions(&grid, 10, 20, 1);
+        assert_eq!(regions.len(), 1);
+        assert_eq!(regions[0], Rect::new((10, 0), (50, 40)));
+    }
+
+    // x方向に周期的なバーを持つ、縦方向の勾配を一切含まない理想的な合成バーコード画像
+    fn vertical_bar_image(width: u32, height: u32) -> GrayImage {
+        GrayImage::from_fn(width, height, |x, _y| {
+            if x % 4 < 2 {
+                image::Luma([0u8])
+            } else {
+                image::Luma([255u8])
+            }
+        })
+    }
+
+    fn flat_image(width: u32, height: u32) -> GrayImage {
+        GrayImage::from_pixel(width, height, image::Luma([128u8]))
+    }
+
+    fn test_config() -> Config {
+        Config {
+            num_sections_width: 4,
+            section_height: 20,
+            threshold: 1.0,
+            min_blob_area: 1,
+            sobel_edge_threshold_fraction: 0.3,
+            anisotropy_ratio_cutoff: 1.2,
+            min_edge_pixels: 5,
+            freq_band_col_guard: 1,
+            freq_band_row_margin: 1,
+        }
+    }
+
+    #[test]
+    fn detect_finds_region_over_vertical_bars() {
+        let img = vertical_bar_image(80, 20);
+        let detector = BarcodeDetector::new(test_config());
+        let regions = detector.detect(&img);
+        assert!(!regions.is_empty());
+        assert!(regions.iter().all(|r| r.width() > 0 && r.height() > 0));
+    }
+
+    #[test]
+    fn detect_returns_empty_for_flat_image() {
+        let img = flat_image(80, 20);
+        let detector = BarcodeDetector::new(test_config());
+        let regions = detector.detect(&img);
+        assert!(regions.is_empty());
+    }
+}